@@ -1,14 +1,15 @@
 /*! Application-specific logic lives here
 
-    **TODO:** Look into moving the argument definition into a
-    [build.rs](https://doc.rust-lang.org/cargo/reference/build-scripts.html) like in the
-    [clap_generate](https://docs.rs/clap_generate/3.0.0-beta.1/clap_generate/fn.generate_to.html)
-    examples so I don't have build the completion generation code into the output binary.
+    Shell completions and the `help2man` source file are generated at compile time by
+    [`build.rs`](https://doc.rust-lang.org/cargo/reference/build-scripts.html) from the same
+    `CliOpts` definition below, instead of shipping `clap`'s completion-writing code into the
+    output binary.
 */
 
 // Parts Copyright 2017-2020, Stephan Sokolow
 
 // Standard library imports
+#[cfg(not(feature = "utf8-paths"))]
 use std::path::PathBuf;
 
 // 3rd-party crate imports
@@ -19,12 +20,42 @@ use structopt::StructOpt;
 use log::{debug, error, info, trace, warn};
 
 // Local Imports
-use crate::helpers::{BoilerplateOpts, HELP_TEMPLATE};
+use crate::helpers::{BoilerplateOpts, OutputSink, HELP_TEMPLATE, META_ACTION_FLAGS};
+use crate::subcommand_defaults;
+#[cfg(not(feature = "utf8-paths"))]
 use crate::validators::path_readable_file;
+#[cfg(feature = "utf8-paths")]
+use crate::validators::path_readable_file_utf8;
 
 /// The verbosity level when no `-q` or `-v` arguments are given, with `0` being `-q`
 pub const DEFAULT_VERBOSITY: u64 = 1;
 
+subcommand_defaults! {
+    /// An example subcommand retained as a template for adding more
+    ///
+    /// Delete this and the matching `Subcommand::Example` variant once your application has a
+    /// real subcommand. Defined via [`subcommand_defaults!`] rather than a bare
+    /// `#[derive(StructOpt)]` so it can't accidentally end up with clap's default help template
+    /// or the crate author's name leaking into its `--help` output.
+    #[allow(clippy::missing_docs_in_private_items)] // TEMPLATE:REMOVE
+    pub struct ExampleArgs {
+        /// An example positional argument
+        pub value: String,
+    }
+}
+
+/// Subcommands recognized by this application
+///
+/// **Caution:** Unlike the `required_unless_one` relief `inpath` gets from meta action flags,
+/// `clap` does *not* automatically relax the top-level `inpath` requirement just because a
+/// subcommand was given. Add `structopt::clap::AppSettings::SubcommandsNegateReqs` to `CliOpts` if
+/// your subcommands should be runnable without also satisfying it.
+#[derive(StructOpt, Debug)]
+pub enum Subcommand {
+    /// An example subcommand retained as a template for adding more
+    Example(ExampleArgs),
+}
+
 /// Command-line argument schema
 ///
 /// ## Relevant Conventions:
@@ -63,18 +94,46 @@ pub struct CliOpts {
 
     /// File(s) to use as input
     ///
-    /// **TODO:** Figure out if there's a way to only enforce constraints on this when not asking
-    ///           to dump completions.
+    /// Required unless a meta action flag (see [`META_ACTION_FLAGS`]) is present, since those
+    /// make the program do something else entirely and exit before reaching the input-processing
+    /// loop in `main`.
+    #[cfg(not(feature = "utf8-paths"))]
     #[structopt(parse(from_os_str),
-                validator_os = path_readable_file)]
+                validator_os = path_readable_file,
+                required_unless_one = META_ACTION_FLAGS)]
     inpath: Vec<PathBuf>,
+
+    /// File(s) to use as input
+    ///
+    /// Built with the `utf8-paths` feature, so non-UTF-8 paths are rejected up front instead of
+    /// being passed through as lossy byte soup. Required unless a meta action flag (see
+    /// [`META_ACTION_FLAGS`]) is present, since those make the program do something else entirely
+    /// and exit before reaching the input-processing loop in `main`.
+    #[cfg(feature = "utf8-paths")]
+    #[structopt(parse(try_from_os_str = path_readable_file_utf8),
+                required_unless_one = META_ACTION_FLAGS)]
+    inpath: Vec<camino::Utf8PathBuf>,
+
+    /// Subcommand to run instead of the default input-processing logic, if any
+    #[structopt(subcommand)]
+    subcommand: Option<Subcommand>,
 }
 
 /// The actual `main()`
 pub fn main(opts: CliOpts) -> Result<()> {
+    #[allow(unused_variables)] // TEMPLATE:REMOVE -- unused until the TODOs below call `sink.emit`
+    let sink = OutputSink::new(opts.boilerplate.message_format);
+
     #[allow(unused_variables, clippy::unimplemented)] // TEMPLATE:REMOVE
-    for inpath in opts.inpath {
-        todo!("Implement application logic")
+    match opts.subcommand {
+        Some(Subcommand::Example(args)) => {
+            todo!("Implement the `example` subcommand, emitting results through `sink`")
+        }
+        None => {
+            for inpath in opts.inpath {
+                todo!("Implement application logic, emitting results through `sink`")
+            }
+        }
     }
 
     Ok(())
@@ -83,12 +142,44 @@ pub fn main(opts: CliOpts) -> Result<()> {
 // Tests go below the code where they'll be out of the way when not the target of attention
 #[cfg(test)]
 mod tests {
-    #[allow(unused_imports)] // TEMPLATE:REMOVE
+    use structopt::StructOpt;
+
     use super::CliOpts;
 
     // TODO: Unit test to verify that the doc comments on `CliOpts` or `BoilerplateOpts` aren't
     // overriding the intended about string.
 
+    #[test]
+    fn inpath_is_required_with_no_meta_action_present() {
+        assert!(CliOpts::from_iter_safe(&["app"]).is_err());
+    }
+
+    #[test]
+    fn inpath_not_required_when_dumping_the_man_page() {
+        assert!(CliOpts::from_iter_safe(&["app", "--dump-man-page", "-"]).is_ok());
+    }
+
+    #[test]
+    fn config_flag_is_accepted_by_the_real_parser() {
+        // `--config`'s value is consumed by `helpers::config_defaults` before `clap` ever sees
+        // this argv, so this only needs to confirm clap recognizes the flag at all instead of
+        // rejecting it with "Found argument '--config' which wasn't expected".
+        assert!(CliOpts::from_iter_safe(
+            &["app", "--config", "some.toml", "--dump-man-page", "-"]).is_ok());
+    }
+
+    #[test]
+    fn subcommand_help_keeps_the_bin_version_header_and_drops_the_author_line() {
+        let err = CliOpts::clap()
+            .get_matches_from_safe(&["app", "example", "--help"])
+            .expect_err("--help always short-circuits into an Err");
+        assert_eq!(err.kind, structopt::clap::ErrorKind::HelpDisplayed);
+        assert!(err.message.starts_with("app-example "),
+            "subcommand help is missing the '{{bin}} {{version}}' header: {:?}", err.message);
+        assert!(!err.message.contains("AUTHOR"),
+            "author section leaked into subcommand help: {:?}", err.message);
+    }
+
     #[test]
     /// Test something
     fn test_something() {
@@ -1,6 +1,12 @@
 /*! Functions and templates which can be imported by `app.rs` to save effort */
 // Copyright 2017-2019, Stephan Sokolow
 
+use std::convert::TryInto;
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::Context;
+use structopt::clap::ArgSettings;
 use structopt::{clap, StructOpt};
 
 /// Modified version of Clap's default template for proper help2man compatibility
@@ -41,7 +47,477 @@ pub struct BoilerplateOpts {
     #[structopt(short, long, value_name = "resolution")]
     pub timestamp: Option<stderrlog::Timestamp>,
 
-    /// Write a completion definition for the specified shell to stdout (bash, zsh, etc.)
-    #[structopt(long, value_name = "shell")]
-    pub dump_completions: Option<clap::Shell>,
+    /// Write a roff(7) man page to the given path, or `-` for stdout, and exit
+    #[structopt(long, value_name = "path")]
+    pub dump_man_page: Option<std::path::PathBuf>,
+
+    /// Select how result and log output are formatted (human, json, short)
+    #[structopt(long, value_name = "format", default_value = "human")]
+    pub message_format: OutputFormat,
+
+    /// Load persistent default values for other options from a TOML or JSON file
+    ///
+    /// The value is actually consumed by [`config_defaults`] before `clap` ever parses argv --
+    /// this field exists only so `clap` recognizes `--config` as a known flag instead of
+    /// rejecting it with "Found argument '--config' which wasn't expected", and so it shows up
+    /// in `--help`.
+    #[structopt(long, value_name = "file")]
+    pub config: Option<std::path::PathBuf>,
+}
+
+/// Long-form flag names of every "meta action" -- a flag that makes the program perform a
+/// one-shot task (generate a man page, etc.) and exit instead of running the normal
+/// input-processing logic in `app::main`
+///
+/// `app::CliOpts::inpath` lists this as its `required_unless_one` set so that clap doesn't demand
+/// input paths when one of these is present. `main::dispatch` checks each meta action's field by
+/// hand before falling through to `app::main`, so a new meta action needs both its flag name added
+/// here and its own branch there.
+pub const META_ACTION_FLAGS: &[&str] = &["dump-man-page"];
+
+/// Define a subcommand's argument struct with the same `template`, `author`, and `ColoredHelp`
+/// defaults `CliOpts` uses at the top level
+///
+/// `StructOpt`/`clap` do not propagate `template` or `author` down into subcommands
+/// ([TeXitoi/structopt#173](https://github.com/TeXitoi/structopt/issues/173),
+/// [clap-rs/clap#1184](https://github.com/clap-rs/clap/issues/1184)), so a subcommand struct
+/// derived by hand silently falls back to clap's default help template (which breaks
+/// `help2man`) and gets the crate author's name injected into its `--help` output. Route every
+/// subcommand's argument struct through this macro instead of deriving `StructOpt` directly so
+/// that footgun isn't reachable.
+#[macro_export]
+macro_rules! subcommand_defaults {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($body:tt)*
+        }
+    ) => {
+        #[derive(structopt::StructOpt, Debug)]
+        #[structopt(template = $crate::helpers::HELP_TEMPLATE, author = "",
+                    global_setting = structopt::clap::AppSettings::ColoredHelp)]
+        $(#[$meta])*
+        $vis struct $name {
+            $($body)*
+        }
+    };
+}
+
+/// How machine-readable a program's result and log output should be
+///
+/// Borrowed from the `--message-format` concept used by cargo and clippy's command surface, so
+/// that downstream tooling gets a stable, parseable contract instead of screen-scraping.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The current human-oriented text output (the default)
+    Human,
+    /// Newline-delimited JSON records, one object per result/event
+    Json,
+    /// A terse, one-line-per-result form intended for shell pipelines
+    Short,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "human" => Ok(OutputFormat::Human),
+            "json" => Ok(OutputFormat::Json),
+            "short" => Ok(OutputFormat::Short),
+            other => Err(format!("invalid --message-format value: {:?} \
+                                   (expected human, json, or short)", other)),
+        }
+    }
+}
+
+/// Routes application result/log output through the format selected by `--message-format`
+///
+/// Construct one from `BoilerplateOpts::message_format` and send all result output through
+/// [`OutputSink::emit`] instead of calling `println!` directly, so `json` mode can serialize it as
+/// structured records on stdout. Log diagnostics can keep using the `log` macros, since
+/// [`init_logging`] already serializes those as structured records on stderr in `json` mode.
+#[derive(Debug)]
+pub struct OutputSink {
+    /// The format chosen via `--message-format`
+    format: OutputFormat,
+}
+
+impl OutputSink {
+    /// Build a sink for the given format
+    #[must_use]
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    /// Emit one result record to stdout, rendered according to `self.format`
+    ///
+    /// `record` must serialize to a JSON object for `json` mode; `short` and `human` are the
+    /// pre-rendered text to use for the other two formats.
+    pub fn emit(&self, record: &impl serde::Serialize, short: &std::fmt::Arguments<'_>,
+            human: &std::fmt::Arguments<'_>) {
+        match self.format {
+            OutputFormat::Human => println!("{}", human),
+            OutputFormat::Short => println!("{}", short),
+            OutputFormat::Json => match serde_json::to_string(record) {
+                Ok(line) => println!("{}", line),
+                Err(e) => eprintln!("error: failed to serialize output record: {}", e),
+            },
+        }
+    }
+
+    /// Emit a log diagnostic, serialized as a structured record on stderr when `self.format` is
+    /// `OutputFormat::Json`, or routed through the normal `log` macros otherwise
+    ///
+    /// Prefer routing diagnostics through the normal `log::error!`/`warn!`/etc. macros and letting
+    /// [`init_logging`] pick the backend; call this directly only for a diagnostic that needs to
+    /// be tied to the same `self.format` a nearby [`OutputSink::emit`] call already used.
+    pub fn log(&self, level: log::Level, message: &std::fmt::Arguments<'_>) {
+        if self.format == OutputFormat::Json {
+            match serde_json::to_string(&log_record_json(level, message)) {
+                Ok(line) => eprintln!("{}", line),
+                Err(e) => eprintln!("error: failed to serialize log record: {}", e),
+            }
+        } else {
+            log::log!(level, "{}", message);
+        }
+    }
+}
+
+/// Build the JSON object shared by [`OutputSink::log`] and [`JsonLogger`], so the
+/// `--message-format json` record shape can't drift between the explicit `sink.log(...)` call
+/// sites and the ambient `log::error!`/`warn!`/etc. calls `JsonLogger` catches
+///
+/// `{:?}`-debug-formatting `message` would escape control/non-ASCII characters as `\u{XXXX}`,
+/// which isn't valid JSON (`serde_json` emits the `\uXXXX` form JSON actually requires), so this
+/// goes through `serde_json::json!` instead of hand-rolling the object.
+fn log_record_json(level: log::Level, message: &std::fmt::Arguments<'_>) -> serde_json::Value {
+    serde_json::json!({
+        "level": level.to_string(),
+        "message": message.to_string(),
+    })
+}
+
+/// A [`log::Log`] backend that serializes every record as a single-line JSON object on stderr
+///
+/// Installed by [`init_logging`] in place of `stderrlog` when `json` is selected, so ordinary
+/// `log::error!`/`warn!`/etc. calls throughout the application come out as structured records
+/// too, instead of only the diagnostics a caller remembered to route through
+/// [`OutputSink::log`] explicitly.
+struct JsonLogger {
+    /// The most verbose level this logger will emit; everything past it is filtered out
+    level: log::LevelFilter,
+}
+
+impl log::Log for JsonLogger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match serde_json::to_string(&log_record_json(record.level(), record.args())) {
+            Ok(line) => eprintln!("{}", line),
+            Err(e) => eprintln!("error: failed to serialize log record: {}", e),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Translate a `stderrlog`-style verbosity count (`-q`/`-v` occurrences, already offset by
+/// [`crate::app::DEFAULT_VERBOSITY`]) into the [`log::LevelFilter`] it corresponds to
+///
+/// Mirrors `stderrlog`'s own `0 => Error, 1 => Warn, 2 => Info, 3 => Debug, _ => Trace` scale, so
+/// [`init_logging`] can offer identical verbosity behaviour regardless of which backend it picks.
+#[must_use]
+pub fn verbosity_to_level_filter(verbosity: u64) -> log::LevelFilter {
+    match verbosity {
+        0 => log::LevelFilter::Error,
+        1 => log::LevelFilter::Warn,
+        2 => log::LevelFilter::Info,
+        3 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    }
+}
+
+/// Initialize the global `log` backend appropriate for `format`: `stderrlog` for `human`/`short`,
+/// or [`JsonLogger`] for `json` so diagnostics come out as structured records instead of going
+/// silent or leaking human-oriented text into a supposedly machine-readable stream
+///
+/// `verbosity` is the raw `-q`/`-v`-derived count (before the `stderrlog`-style off-by-one
+/// subtraction), with `0` meaning "no output at all".
+pub fn init_logging(format: OutputFormat, verbosity: u64, module: &str,
+        timestamp: stderrlog::Timestamp) -> anyhow::Result<()> {
+    let stderrlog_verbosity = verbosity.saturating_sub(1);
+
+    if format == OutputFormat::Json {
+        let level = if verbosity == 0 {
+            log::LevelFilter::Off
+        } else {
+            verbosity_to_level_filter(stderrlog_verbosity)
+        };
+        log::set_max_level(level);
+        log::set_boxed_logger(Box::new(JsonLogger { level }))
+            .context("Failed to initialize logging output")
+    } else {
+        stderrlog::new()
+            .module(module)
+            .quiet(verbosity == 0)
+            .verbosity(stderrlog_verbosity.try_into().context("Verbosity too high")?)
+            .timestamp(timestamp)
+            .init()
+            .context("Failed to initialize logging output")
+    }
+}
+
+/// Escape the `roff(7)` control characters (every `\`, plus a leading `.` or `'`) in a line of text
+///
+/// roff treats a `.` or `'` at the start of a line as a request, so anything coming out of
+/// `--help` text (which may legitimately start a line with either) has to be neutralized before
+/// being embedded in the page.
+fn escape_roff_line(line: &str) -> String {
+    let mut escaped = line.replace('\\', "\\e");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        escaped.insert(0, '\\');
+        escaped.insert(1, '&');
+    }
+    escaped
+}
+
+/// Scan argv for the value of a `--flag`-style option before the real argument parser runs
+///
+/// Uses the same technique as Clippy's `arg_value` helper, which scans rustc's raw command line
+/// for a flag's value before the real `rustc` parse happens. Handles both `--flag=value` and
+/// `--flag value` forms. If `flag` appears more than once, the last occurrence wins, matching how
+/// `main()` has `clap` resolve repeated non-multiple options. Scanning stops at a bare `--`, since everything
+/// after it is a positional argument and can never be mistaken for `flag`. A trailing `--flag`
+/// with no following value is ignored rather than treated as an error, since the real parser will
+/// report that properly once it runs.
+fn scan_arg_value<'a, I>(args: I, flag: &str) -> Option<String>
+where
+    I: IntoIterator<Item = &'a OsString>,
+{
+    let mut found = None;
+    let mut args = args.into_iter().map(|arg| arg.to_string_lossy().into_owned());
+    let prefix = format!("{}=", flag);
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if let Some(value) = arg.strip_prefix(&prefix) {
+            found = Some(value.to_owned());
+        } else if arg == flag {
+            if let Some(value) = args.next() {
+                found = Some(value);
+            }
+        }
+    }
+
+    found
+}
+
+/// Locate a `--config <file>`/`APP_CONFIG`-provided config file and turn its contents into a
+/// prefix of CLI flags representing persistent defaults
+///
+/// Returns `None` if neither the flag nor the environment variable name a config file, so the
+/// common case of running without one stays a single `CliOpts::from_args()` call. When combined
+/// the way `main()` does it, precedence ends up CLI flags > `--config`/`APP_CONFIG` file >
+/// built-in `StructOpt` defaults, because the returned flags are spliced in *before* the user's
+/// real argv, and `main()` parses with `AllArgsOverrideSelf` so a later occurrence of a flag wins
+/// over an earlier one.
+pub fn config_defaults(args: &[OsString]) -> anyhow::Result<Option<Vec<OsString>>> {
+    let path = scan_arg_value(args, "--config")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("APP_CONFIG").map(std::path::PathBuf::from));
+
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+
+    let is_json = path.extension().and_then(std::ffi::OsStr::to_str) == Some("json");
+    let values: std::collections::BTreeMap<String, toml::Value> = if is_json {
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {} as JSON", path.display()))?
+    } else {
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse {} as TOML", path.display()))?
+    };
+
+    let mut flags = Vec::with_capacity(values.len() * 2);
+    for (key, value) in values {
+        let flag = OsString::from(format!("--{}", key.replace('_', "-")));
+
+        // `toml::Value::to_string()` goes through `Display`, which quotes string scalars and
+        // can't express a switch, so pull the scalar out by hand instead: bare text for strings,
+        // the bare flag (no value) for `true`, and no flag at all for `false`, rather than
+        // `--flag "value"` (quotes and all) or `--flag true`/`--flag false`.
+        match value {
+            toml::Value::String(s) => {
+                flags.push(flag);
+                flags.push(OsString::from(s));
+            },
+            toml::Value::Boolean(true) => flags.push(flag),
+            toml::Value::Boolean(false) => {},
+            other => {
+                flags.push(flag);
+                flags.push(OsString::from(other.to_string()));
+            },
+        }
+    }
+    Ok(Some(flags))
+}
+
+/// Render the `.SH SYNOPSIS` usage line for `app`, using its schema rather than its pre-rendered
+/// `USAGE:` text so flag/option/positional placeholders stay roff-safe
+fn render_synopsis(app: &clap::App<'_, '_>, bin_name: &str) -> String {
+    let mut usage = bin_name.to_owned();
+
+    if !app.p.flags.is_empty() || !app.p.opts.is_empty() {
+        usage.push_str(" [OPTIONS]");
+    }
+
+    for positional in app.p.positionals.values() {
+        let placeholder = positional.b.name.to_uppercase();
+        let multiple = positional.b.settings.is_set(ArgSettings::Multiple);
+        if positional.b.settings.is_set(ArgSettings::Required) {
+            usage.push_str(&format!(" <{}>{}", placeholder, if multiple { "..." } else { "" }));
+        } else {
+            usage.push_str(&format!(" [{}]{}", placeholder, if multiple { "..." } else { "" }));
+        }
+    }
+
+    if !app.p.subcommands.is_empty() {
+        usage.push_str(" [SUBCOMMAND]");
+    }
+
+    format!("{}\n", escape_roff_line(&usage))
+}
+
+/// Render one `.TP`/flag-summary/help-text entry for a flag or option
+fn render_option_entry(short: Option<char>, long: Option<&str>, value_name: Option<&str>,
+        help: Option<&str>) -> String {
+    let mut flags = Vec::new();
+    if let Some(short) = short {
+        flags.push(format!("-{}", short));
+    }
+    if let Some(long) = long {
+        flags.push(format!("--{}", long));
+    }
+    let mut summary = flags.join(", ");
+    if let Some(value_name) = value_name {
+        summary.push_str(&format!(" <{}>", value_name));
+    }
+
+    let mut entry = format!(".TP\n\\fB{}\\fR\n", escape_roff_line(&summary));
+    if let Some(help) = help {
+        entry.push_str(&format!("{}\n", escape_roff_line(help)));
+    }
+    entry
+}
+
+/// Render the `.SH OPTIONS` section body for `app`'s flags and options
+fn render_options_section(app: &clap::App<'_, '_>) -> String {
+    let mut body = String::new();
+
+    for flag in &app.p.flags {
+        body.push_str(&render_option_entry(flag.s.short, flag.s.long, None, flag.b.help));
+    }
+
+    for opt in &app.p.opts {
+        let value_name = opt.v.val_names.as_ref()
+            .and_then(|names| names.values().next().copied())
+            .unwrap_or(opt.b.name);
+        body.push_str(&render_option_entry(opt.s.short, opt.s.long, Some(value_name), opt.b.help));
+    }
+
+    body
+}
+
+/// Render the `.SH ARGS` section body for `app`'s positional arguments
+fn render_args_section(app: &clap::App<'_, '_>) -> String {
+    let mut body = String::new();
+
+    for positional in app.p.positionals.values() {
+        body.push_str(&format!(".TP\n\\fB{}\\fR\n", escape_roff_line(&positional.b.name.to_uppercase())));
+        if let Some(help) = positional.b.help {
+            body.push_str(&format!("{}\n", escape_roff_line(help)));
+        }
+    }
+
+    body
+}
+
+/// Render the `.SH SUBCOMMANDS` section body, listing each subcommand's name and summary
+fn render_subcommands_section(app: &clap::App<'_, '_>) -> String {
+    let mut body = String::new();
+
+    for subcommand in &app.p.subcommands {
+        body.push_str(&format!(".TP\n\\fB{}\\fR\n", escape_roff_line(&subcommand.p.meta.name)));
+        if let Some(about) = subcommand.p.meta.about {
+            body.push_str(&format!("{}\n", escape_roff_line(about)));
+        }
+    }
+
+    body
+}
+
+/// Render a `roff(7)` man page for the given `clap::App`
+///
+/// Walks the same argument/subcommand schema `StructOpt` built for `--help` (via `App::p`, which
+/// `clap` keeps public for exactly this kind of introspection), rather than parsing the rendered
+/// `--help` text, so the `NAME`/`SYNOPSIS`/`OPTIONS`/`ARGS` sections can't drift out of sync with
+/// whatever layout a future help template happens to use.
+pub fn render_man_page(app: &clap::App<'_, '_>) -> String {
+    let name = app.p.meta.name.clone();
+    let bin_name = app.p.meta.bin_name.clone().unwrap_or_else(|| name.clone());
+    let description = app.p.meta.long_about.or(app.p.meta.about);
+    // The `about`/`long_about` text may start with a blank line (the `\n`-injection workaround
+    // `HELP_TEMPLATE` replaces), so skip past any to find the actual one-line summary.
+    let summary = description.and_then(|text| text.lines().find(|line| !line.trim().is_empty()));
+
+    let mut page = String::new();
+    page.push_str(&format!(".TH {} 1\n", bin_name.to_uppercase()));
+
+    page.push_str(".SH NAME\n");
+    page.push_str(&match summary {
+        Some(summary) => format!("{} \\- {}\n", escape_roff_line(&name), escape_roff_line(summary)),
+        None => format!("{}\n", escape_roff_line(&name)),
+    });
+
+    page.push_str(".SH SYNOPSIS\n");
+    page.push_str(&render_synopsis(app, &bin_name));
+
+    if let Some(description) = description {
+        page.push_str(".SH DESCRIPTION\n");
+        for line in description.lines().skip_while(|line| line.trim().is_empty()) {
+            page.push_str(&format!("{}\n", escape_roff_line(line)));
+        }
+    }
+
+    let options = render_options_section(app);
+    if !options.is_empty() {
+        page.push_str(".SH OPTIONS\n");
+        page.push_str(&options);
+    }
+
+    let args = render_args_section(app);
+    if !args.is_empty() {
+        page.push_str(".SH ARGS\n");
+        page.push_str(&args);
+    }
+
+    let subcommands = render_subcommands_section(app);
+    if !subcommands.is_empty() {
+        page.push_str(".SH SUBCOMMANDS\n");
+        page.push_str(&subcommands);
+    }
+
+    page
 }
@@ -12,8 +12,8 @@ This file provided by [rust-cli-boilerplate](https://github.com/ssokolow/rust-cl
 #![forbid(unsafe_code)] // Enforce my policy of only allowing it in my own code as a last resort
 
 // stdlib imports
-use std::convert::TryInto;
 use std::io;
+use std::panic;
 
 // 3rd-party imports
 use anyhow::{Context, Result};
@@ -22,15 +22,52 @@ use structopt::{clap, StructOpt};
 // Local imports
 mod app;
 mod helpers;
+mod process;
 mod validators;
 
-/// Boilerplate to parse command-line arguments, set up logging, and handle bubbled-up `Error`s.
+/// Install a friendlier panic report in place of the default `thread 'main' panicked` output
 ///
-/// See `app::main` for the application-specific logic.
-fn main() -> Result<()> {
-    // Parse command-line arguments (exiting on parse error, --version, or --help)
-    let opts = app::CliOpts::from_args();
+/// Modeled on the bug-report hook Clippy's driver installs for internal compiler panics: capture
+/// the payload and location, log it through `sink` so it routes through whatever logging/output
+/// format is active, print a short report telling the user how to file a bug instead of a bare
+/// Rust backtrace, and dump a full backtrace when asked. Must be installed after logging is
+/// initialized (via [`helpers::init_logging`]) so the logged record routes correctly.
+///
+/// `show_full_backtrace` additionally forces the full backtrace even without `RUST_BACKTRACE` set,
+/// for callers running at a high enough verbosity that they're already asking for maximum detail.
+fn install_panic_hook(sink: helpers::OutputSink, show_full_backtrace: bool) {
+    panic::set_hook(Box::new(move |info: &panic::PanicInfo<'_>| {
+        let location = info.location().map_or_else(
+            || "<unknown location>".to_owned(),
+            |l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        let payload = info.payload().downcast_ref::<&str>().copied()
+            .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Box<dyn Any>");
+
+        sink.log(log::Level::Error,
+            &format_args!("internal error at {}: {}", location, payload));
+
+        eprintln!();
+        eprintln!("error: internal error in {} {}: panicked at {}",
+            clap::crate_name!(), env!("CARGO_PKG_VERSION"), location);
+        eprintln!("note: {}", payload);
+        eprintln!("note: this is a bug. Please file an issue with steps to reproduce.");
+        eprintln!("note: platform: {}-{}", std::env::consts::ARCH, std::env::consts::OS);
 
+        if show_full_backtrace || std::env::var_os("RUST_BACKTRACE").is_some() {
+            eprintln!("{:?}", std::backtrace::Backtrace::force_capture());
+        } else {
+            eprintln!("note: re-run with RUST_BACKTRACE=1 (or -vv) for a full backtrace");
+        }
+    }));
+}
+
+/// Set up logging and the panic hook, then dispatch to `app::main` or one of the meta-actions
+///
+/// This is the part of the boilerplate that needs an already-parsed `CliOpts` to do its work, so
+/// both the normal `main` below and the `paw`-driven one (which receives `CliOpts` from `paw`
+/// rather than parsing it itself) can share it.
+fn dispatch(opts: app::CliOpts) -> Result<()> {
     // Configure logging output so that -q is "decrease verbosity" rather than instant silence
     let verbosity = opts
         .boilerplate
@@ -38,21 +75,31 @@ fn main() -> Result<()> {
         .saturating_add(app::DEFAULT_VERBOSITY)
         .saturating_sub(opts.boilerplate.quiet);
 
-    stderrlog::new()
-        .module(module_path!())
-        .quiet(verbosity == 0)
-        .verbosity(verbosity.saturating_sub(1).try_into().context("Verbosity too high")?)
-        .timestamp(opts.boilerplate.timestamp.unwrap_or(stderrlog::Timestamp::Off))
-        .init()
-        .context("Failed to initialize logging output")?;
-
-    // If requested, generate shell completions and then exit with status of "success"
-    if let Some(shell) = opts.boilerplate.dump_completions {
-        app::CliOpts::clap().gen_completions_to(
-            app::CliOpts::clap().get_bin_name().unwrap_or_else(|| clap::crate_name!()),
-            shell,
-            &mut io::stdout(),
-        );
+    // In `json` mode, `init_logging` installs a backend that serializes every `log::error!`/
+    // `warn!`/etc. record as a structured line on stderr instead of handing them to `stderrlog`.
+    helpers::init_logging(opts.boilerplate.message_format, verbosity, module_path!(),
+        opts.boilerplate.timestamp.unwrap_or(stderrlog::Timestamp::Off))?;
+
+    // Replace the default panic output with a friendlier report now that logging is live. Route
+    // it through a sink for the selected format so it's captured as a structured record in `json`
+    // mode too, and force the full backtrace at `-vv` and above (matching the `verbosity` that
+    // already turns on info-level logging) even if the user forgot `RUST_BACKTRACE`.
+    let sink = helpers::OutputSink::new(opts.boilerplate.message_format);
+    let show_full_backtrace = verbosity >= app::DEFAULT_VERBOSITY.saturating_add(2);
+    install_panic_hook(sink, show_full_backtrace);
+
+    // Shell completions are generated at compile time by `build.rs` now, so there's no runtime
+    // `--dump-completions` flag left to handle here; see `target/dist` (or `$DIST_DIR`) for them.
+    if let Some(ref path) = opts.boilerplate.dump_man_page {
+        // Likewise for a roff(7) man page, rendered directly instead of depending on `help2man`
+        let page = helpers::render_man_page(&app::CliOpts::clap());
+        if path.as_os_str() == "-" {
+            use std::io::Write;
+            io::stdout().write_all(page.as_bytes()).context("Failed to write man page to stdout")?;
+        } else {
+            std::fs::write(path, page)
+                .with_context(|| format!("Failed to write man page to {}", path.display()))?;
+        }
         Ok(())
     } else {
         // Run the actual `main` and rely on `impl Termination` to provide a simple, concise way to
@@ -63,4 +110,42 @@ fn main() -> Result<()> {
     }
 }
 
+/// Boilerplate to parse command-line arguments, set up logging, and handle bubbled-up `Error`s.
+///
+/// See `app::main` for the application-specific logic.
+///
+/// **Note:** Built with the `paw` feature, parsing is instead driven by `paw::main` below, since
+/// `paw`'s `ParseArgs` contract hands `main` an already-parsed `CliOpts` and doesn't leave room to
+/// splice in `--config`/`APP_CONFIG` defaults ahead of the real argv the way `dispatch` otherwise
+/// assumes. Don't enable `paw` if you need config-file layering.
+#[cfg(not(feature = "paw"))]
+fn main() -> Result<()> {
+    // Layer in persistent defaults from a `--config`/`APP_CONFIG` file, if any, ahead of the real
+    // argv so explicit CLI flags (which `clap` resolves by last-occurrence-wins) still take
+    // precedence over them.
+    let mut args: Vec<_> = std::env::args_os().collect();
+    if let Some(config_flags) = helpers::config_defaults(&args[1..])? {
+        args.splice(1..1, config_flags);
+    }
+
+    // Parse command-line arguments (exiting on parse error, --version, or --help). `clap` rejects a
+    // repeated non-multiple option by default, so `AllArgsOverrideSelf` is what lets an explicit
+    // CLI flag override the same flag spliced in from the config file above.
+    let matches = app::CliOpts::clap()
+        .setting(clap::AppSettings::AllArgsOverrideSelf)
+        .get_matches_from(args);
+    let opts = app::CliOpts::from_clap(&matches);
+
+    dispatch(opts)
+}
+
+/// `paw`-driven entry point: `paw::main` parses `CliOpts` via `StructOpt`'s `paw` feature and
+/// hands it straight to this function, removing the need for projects built from this template to
+/// hand-write the parse-and-dispatch glue above.
+#[cfg(feature = "paw")]
+#[paw::main]
+fn main(opts: app::CliOpts) -> Result<()> {
+    dispatch(opts)
+}
+
 // vim: set sw=4 sts=4 expandtab :
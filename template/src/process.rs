@@ -0,0 +1,60 @@
+/*! Helpers for invoking external commands with rich, chained error context */
+// Copyright 2017-2020, Stephan Sokolow
+
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+
+/// Unwrap a `Result`, printing the failing expression and its error before panicking
+///
+/// Modeled on the `t!` macro used throughout rustbuild, for fatal internal operations where
+/// bubbling the error up through `main`'s `Result` would be more ceremony than it's worth.
+#[macro_export]
+macro_rules! t {
+    ($e:expr) => {
+        match $e {
+            Ok(value) => value,
+            Err(e) => panic!("{} failed: {}", stringify!($e), e),
+        }
+    };
+}
+
+/// Pull the program name and argument list out of a `Command` for error messages
+fn describe(cmd: &Command) -> (String, Vec<String>) {
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let args = cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+    (program, args)
+}
+
+/// Run `cmd`, inheriting stdio, and error out (with the program name, arguments, and exit status)
+/// if it exits with a nonzero status
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn run(mut cmd: Command) -> Result<()> {
+    let (program, args) = describe(&cmd);
+    let status = cmd.status().with_context(|| format!("Failed to execute {}", program))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("{} {:?} exited with {}", program, args, status))
+    }
+}
+
+/// Run `cmd`, capturing stdout as a trimmed `String`, and error out (with the program name,
+/// arguments, exit status, and captured stderr) if it exits with a nonzero status or its stdout
+/// isn't valid UTF-8
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn output_string(mut cmd: Command) -> Result<String> {
+    let (program, args) = describe(&cmd);
+    let output = cmd.output().with_context(|| format!("Failed to execute {}", program))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("{} {:?} exited with {}{}", program, args, output.status,
+            if stderr.trim().is_empty() { String::new() } else { format!(": {}", stderr.trim()) }));
+    }
+
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("{:?} produced non-UTF-8 output", program))
+        .map(|stdout| stdout.trim().to_owned())
+}
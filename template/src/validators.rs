@@ -1,11 +1,12 @@
 /*! Validator functions suitable for use with `Clap` and `StructOpt` */
 // Copyright 2017-2020, Stephan Sokolow
 
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::fs::File;
-use std::path::{Component, Path};
+use std::path::{Component, Path, PathBuf};
 
 use faccess::PathExt;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
 
 /// Special filenames which cannot be used for real files under Win32
 ///
@@ -28,8 +29,6 @@ pub const RESERVED_DOS_FILENAMES: &[&str] = &["AUX", "CON", "NUL", "PRN",   // C
     "CLOCK$", "$IDLE$", "CONFIG$", "KEYBD$", "LST", "SCREEN$"];
 
 /// Test that the given path *should* be writable
-///
-/// **TODO:** Implement Windows tests for this.
 #[allow(dead_code)] // TEMPLATE:REMOVE
 #[cfg(unix)]
 pub fn path_output_dir<P: AsRef<Path> + ?Sized>(value: &P) -> Result<(), OsString> {
@@ -46,6 +45,165 @@ pub fn path_output_dir<P: AsRef<Path> + ?Sized>(value: &P) -> Result<(), OsStrin
     Err(format!("Would be unable to write to destination directory: {}", path.display()).into())
 }
 
+/// Test that the given path *should* be writable
+///
+/// Windows ACLs make a simple permission-bit check unreliable, so this probes by attempting to
+/// create and immediately delete a uniquely-named temporary entry inside the directory. If the
+/// probe is refused with `PermissionDenied`, the answer from [`faccess::PathExt::writable`] is
+/// used instead; any other probe error is reported as the directory being unwritable.
+#[allow(dead_code)] // TEMPLATE:REMOVE
+#[cfg(windows)]
+pub fn path_output_dir<P: AsRef<Path> + ?Sized>(value: &P) -> Result<(), OsString> {
+    let path = value.as_ref();
+
+    // Unpaired UTF-16 surrogates can't round-trip through the `File::create` probe below, and
+    // device paths (`\\.\`, `CONIN$`, `CONOUT$`) aren't real directories even when `is_dir()`
+    // might claim otherwise.
+    let lossy = path.as_os_str().to_str().ok_or_else(|| {
+        OsString::from(format!("Path contains unpaired surrogates: {:?}", path))
+    })?;
+    if lossy.starts_with(r"\\.\")
+            || ["CONIN$", "CONOUT$"].iter().any(|&name| lossy.eq_ignore_ascii_case(name)) {
+        return Err(format!("Not a real directory (device path): {}", path.display()).into());
+    }
+
+    if !path.is_dir() {
+        return Err(format!("Not a directory: {}", path.display()).into());
+    }
+
+    let probe_name = format!(".{:x}-{:x}.tmp", std::process::id(), probe_nonce());
+    let probe_path = path.join(probe_name);
+
+    match File::create(&probe_path) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe_path);
+            Ok(())
+        },
+        Err(ref e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            if path.writable() {
+                Ok(())
+            } else {
+                Err(format!("Would be unable to write to destination directory: {}",
+                    path.display()).into())
+            }
+        },
+        Err(e) => Err(format!("Would be unable to write to destination directory: {}: {}",
+            path.display(), e).into()),
+    }
+}
+
+/// A best-effort unique suffix for [`path_output_dir`]'s write probe filename
+#[cfg(windows)]
+fn probe_nonce() -> u128 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_nanos())
+}
+
+/// The given path, or its nearest existing ancestor, should be writable
+///
+/// ## Use For:
+///  * Output file paths, including ones that don't exist yet, as opposed to
+///    [`path_output_dir`](fn.path_output_dir.html), which requires an already-existing directory.
+///
+/// ## Cautions:
+///  * As with `path_readable_file`, relying on this to remain true will introduce a race
+///    condition. This validator is intended only to let your program exit as quickly as possible
+///    in the case of an obviously bad output path.
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn path_writable<P: AsRef<Path> + ?Sized>(value: &P) -> Result<(), OsString> {
+    let path = value.as_ref();
+
+    if path.exists() {
+        return if path.writable() {
+            Ok(())
+        } else {
+            Err(format!("Would be unable to write to: {}", path.display()).into())
+        };
+    }
+
+    // The target doesn't exist yet (the common case for an output path), so walk up to the
+    // nearest existing ancestor and test that instead.
+    for ancestor in path.ancestors().skip(1) {
+        if ancestor.exists() {
+            return if ancestor.writable() {
+                Ok(())
+            } else {
+                Err(format!("Would be unable to write to: {}", ancestor.display()).into())
+            };
+        }
+    }
+
+    Err(format!("{}: No existing ancestor directory found", path.display()).into())
+}
+
+/// Convert a Windows verbatim (`\\?\`) path back to legacy form whenever doing so is unambiguous
+///
+/// Identity on non-Windows platforms. A path built under the `\\?\` prefix (e.g. because it
+/// exceeded `MAX_PATH`) is opaque to legacy Win32 programs, so a tool that wants to hand its
+/// output paths to other programs should downgrade them back to the widely-compatible form
+/// whenever that can't change what the path refers to.
+///
+/// `Prefix::VerbatimDisk` becomes `C:\...` and `Prefix::VerbatimUNC` becomes `\\server\share\...`.
+/// Bare `Prefix::Verbatim(..)` and device paths (`\\.\`) are left untouched, since they have no
+/// legacy equivalent. The downgrade is only performed when every `Normal` component would pass
+/// [`filename_valid_portable`](fn.filename_valid_portable.html) (so no illegal characters, no
+/// reserved DOS name, no trailing space/period), there are no `.`/`..` components that would
+/// re-canonicalize differently, and the rebuilt path stays under the legacy 260-character limit;
+/// otherwise the original verbatim path is returned unchanged so UNC-only-reachable paths stay
+/// reachable.
+#[cfg(windows)]
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn normalize_portable(path: &Path) -> PathBuf {
+    use std::path::Prefix;
+
+    let mut components = path.components();
+    let prefix = match components.next() {
+        Some(Component::Prefix(prefix)) => prefix,
+        _ => return path.to_owned(),
+    };
+
+    let rebuilt_prefix = match prefix.kind() {
+        Prefix::VerbatimDisk(letter) => format!("{}:\\", letter as char),
+        Prefix::VerbatimUNC(server, share) => {
+            format!("\\\\{}\\{}\\", server.to_string_lossy(), share.to_string_lossy())
+        },
+        // `Verbatim(..)` and device paths (`\\.\`) have no legacy equivalent
+        _ => return path.to_owned(),
+    };
+
+    let mut rest = PathBuf::new();
+    for component in components {
+        match component {
+            Component::Normal(part) => {
+                if filename_valid_portable(part).is_err() {
+                    return path.to_owned();
+                }
+                rest.push(part);
+            },
+            // `.`/`..` could re-canonicalize to a different path once the verbatim prefix (which
+            // disables canonicalization) is removed
+            Component::CurDir | Component::ParentDir => return path.to_owned(),
+            Component::RootDir | Component::Prefix(_) => {},
+        }
+    }
+
+    let mut result = PathBuf::from(rebuilt_prefix);
+    result.push(rest);
+
+    if result.as_os_str().len() >= 260 {
+        return path.to_owned();
+    }
+
+    result
+}
+
+/// Identity on non-Windows platforms; see the `#[cfg(windows)]` version for the real behaviour
+#[cfg(not(windows))]
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn normalize_portable(path: &Path) -> PathBuf {
+    path.to_owned()
+}
+
 /// The given path is a file that can be opened for reading
 ///
 /// ## Use For:
@@ -60,8 +218,8 @@ pub fn path_output_dir<P: AsRef<Path> + ?Sized>(value: &P) -> Result<(), OsStrin
 ///  * Interpret a value of `-` to mean "read from `stdin`" if feasible.
 ///    [[2]](http://pubs.opengroup.org/onlinepubs/9699919799/basedefs/V1_chap12.html)
 ///
-/// **TODO:** Provide an alternative variant of this which accepts `-` regardless of whether a file
-/// of that name exists.
+/// See [`path_readable_file_or_stdin`](fn.path_readable_file_or_stdin.html) for a variant of this
+/// which accepts `-` regardless of whether a file of that name exists.
 ///
 /// **Note:** The following command-lines, which interleave files and `stdin`, are a good test of
 /// how the above conventions should interact:
@@ -94,6 +252,81 @@ pub fn path_readable_file<P: AsRef<Path> + ?Sized>(value: &P)
 
 // TODO: Implement path_readable_dir and path_readable for --recurse use-cases
 
+/// `path_readable_file`, but treating a literal `-` as "read from stdin" regardless of whether a
+/// file of that name exists
+///
+/// ## Use For:
+///  * Input file paths, for commands that interleave `stdin` with real files on the command
+///    line (see the interleaved-`stdin` command lines documented on
+///    [`path_readable_file`](fn.path_readable_file.html)).
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn path_readable_file_or_stdin<P: AsRef<Path> + ?Sized>(value: &P)
+        -> std::result::Result<(), OsString> {
+    let path = value.as_ref();
+    if path.as_os_str() == "-" {
+        return Ok(());
+    }
+    path_readable_file(path)
+}
+
+/// `path_readable_file`, but requiring the path to be valid UTF-8 and returning a
+/// [`camino::Utf8PathBuf`] instead of a [`std::path::PathBuf`]
+///
+/// ## Use For:
+///  * Input file paths, when compiled with the `utf8-paths` feature, so the rest of the program
+///    can work with [`camino::Utf8Path`] instead of doing lossy `to_string_lossy` round-trips.
+///
+/// ## Cautions:
+///  * Rejects otherwise-valid paths containing non-UTF-8 bytes up front with a clear error rather
+///    than letting them through as a "bag of bytes". Don't enable `utf8-paths` for tools that must
+///    tolerate arbitrary filenames.
+///  * Shares the same race-condition caveats as `path_readable_file`, which this calls internally.
+#[cfg(feature = "utf8-paths")]
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn path_readable_file_utf8(value: &OsStr)
+        -> std::result::Result<camino::Utf8PathBuf, OsString> {
+    let path = camino::Utf8PathBuf::from_path_buf(PathBuf::from(value))
+        .map_err(|path| format!("Path is not valid UTF-8: {}", path.display()))?;
+    path_readable_file(&path)?;
+    Ok(path)
+}
+
+/// Where a `-`-aware input argument resolved to, once parsed by [`InputSource::resolve`]
+#[allow(dead_code)] // TEMPLATE:REMOVE
+#[derive(Debug, PartialEq, Eq)]
+pub enum InputSource {
+    /// The argument was the literal value `-`
+    Stdin,
+    /// The argument was any other value, to be treated as a path
+    Path(PathBuf),
+}
+
+impl InputSource {
+    /// Resolve a raw `-`-aware argument value into an `InputSource`
+    ///
+    /// Because `-` can legitimately appear more than once on a single command line (see
+    /// [`path_readable_file`](fn.path_readable_file.html)'s interleaved-`stdin` examples), this
+    /// takes no steps to deduplicate; each occurrence resolves independently.
+    #[allow(dead_code)] // TEMPLATE:REMOVE
+    #[must_use]
+    pub fn resolve(value: &OsStr) -> Self {
+        if value == "-" {
+            InputSource::Stdin
+        } else {
+            InputSource::Path(PathBuf::from(value))
+        }
+    }
+
+    /// Open this input source for reading
+    #[allow(dead_code)] // TEMPLATE:REMOVE
+    pub fn open(&self) -> std::io::Result<Box<dyn std::io::Read>> {
+        match self {
+            InputSource::Stdin => Ok(Box::new(std::io::stdin())),
+            InputSource::Path(path) => Ok(Box::new(File::open(path)?)),
+        }
+    }
+}
+
 /// The given path is valid on all major filesystems and OSes
 ///
 /// ## Use For:
@@ -266,6 +499,339 @@ pub fn filename_valid_portable<P: AsRef<Path> + ?Sized>(value: &P) -> Result<(),
     }
 }
 
+/// How strictly to treat Unicode normalization form when validating a filename
+///
+/// A filename written in NFD (decomposed) form on Linux can read back as a *different* string --
+/// or silently collide with an already-existing NFC file -- once it reaches macOS, where
+/// HFS+/APFS compose everything to NFC on the way out.
+/// [[1]](https://news.ycombinator.com/item?id=16993687)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NormalizationPolicy {
+    /// Don't check normalization form at all
+    Ignore,
+    /// Reject filenames that aren't already in Unicode Normalization Form C
+    RequireNfc,
+}
+
+/// Return the NFC-folded form of a filename, for callers that want to repair rather than reject
+///
+/// Falls back to returning `name` unchanged if it isn't valid Unicode, since there's nothing
+/// meaningful to normalize in that case.
+#[allow(dead_code)] // TEMPLATE:REMOVE
+#[must_use]
+pub fn normalize_filename(name: &OsStr) -> OsString {
+    match name.to_str() {
+        Some(string) => string.nfc().collect::<String>().into(),
+        None => name.to_owned(),
+    }
+}
+
+/// `filename_valid_portable`, plus a check of the component's Unicode normalization form
+///
+/// Checks [`NormalizationPolicy::RequireNfc`] *in addition to* everything
+/// [`filename_valid_portable`](fn.filename_valid_portable.html) already checks; pass
+/// [`NormalizationPolicy::Ignore`] to get identical behaviour to that function.
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn filename_valid_portable_normalized<P: AsRef<Path> + ?Sized>(value: &P,
+        policy: NormalizationPolicy) -> Result<(), OsString> {
+    let path = value.as_ref();
+    filename_valid_portable(path)?;
+
+    if policy == NormalizationPolicy::RequireNfc {
+        if let Some(string) = path.as_os_str().to_str() {
+            // `IsNormalized::Maybe` means "inconclusive, run the full check" rather than "not
+            // normalized" -- treating it as a rejection would falsely refuse strings that the
+            // quick check couldn't rule on but that are, in fact, already NFC.
+            let is_nfc = match is_nfc_quick(string.chars()) {
+                IsNormalized::Yes => true,
+                IsNormalized::No => false,
+                IsNormalized::Maybe => string.chars().eq(string.nfc()),
+            };
+            if !is_nfc {
+                return Err(format!(
+                    "Component is not in Unicode Normalization Form C: {:?}", path).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Maximum filename length, in UCS-2 code units, guaranteed by the Joliet specification
+///
+/// Real-world Joliet writers (including Microsoft's and `mkisofs`'s) tolerate up to 103 or 110
+/// characters in practice, but 64 is the only figure the specification itself guarantees.
+pub const MAX_JOLIET_LEN: usize = 64;
+
+/// UDF's maximum total path length, in bytes
+pub const MAX_UDF_PATH_LEN: usize = 1023;
+
+/// The given path is valid for recording on plain ISO 9660 media (Level 1/2), without the Joliet
+/// or Rock Ridge extensions
+///
+/// ## Use For:
+///  * Output paths destined for optical media meant to be read by the widest range of legacy
+///    hardware/firmware, such as console and embedded CD/DVD drives.
+///
+/// ## Design Considerations:
+/// [[1]](https://www.boost.org/doc/libs/1_36_0/libs/filesystem/doc/portability_guide.htm)
+///  * Components are limited to uppercase `A`-`Z`, `0`-`9`, `_`, and (in the filename only) a
+///    single `.` separating an up-to-8-character name from an up-to-3-character extension
+///    (Level 1), or an up-to-31-character combined name (Level 2).
+///  * Directory nesting is limited to 8 levels deep.
+///  * Directory component names may not themselves contain a `.`.
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn path_valid_iso9660<P: AsRef<Path> + ?Sized>(value: &P) -> Result<(), OsString> {
+    let path = value.as_ref();
+
+    let components: Vec<&OsStr> = path.components()
+        .filter_map(|c| if let Component::Normal(string) = c { Some(string) } else { None })
+        .collect();
+
+    if components.len() > 8 {
+        return Err(format!("Directory nesting exceeds ISO 9660's 8-level limit: {:?}", path).into());
+    }
+
+    let last_index = components.len().saturating_sub(1);
+    for (index, component) in components.iter().enumerate() {
+        let is_filename = index == last_index;
+        let name = component.to_str().ok_or_else(|| {
+            OsString::from(format!("ISO 9660 requires ASCII component names: {:?}", component))
+        })?;
+
+        if !is_filename && name.contains('.') {
+            return Err(format!("ISO 9660 directory names cannot contain '.': {:?}", component).into());
+        }
+        if name.matches('.').count() > 1 {
+            return Err(format!("ISO 9660 names may contain only a single '.': {:?}", component).into());
+        }
+        if !name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_' || c == '.') {
+            #[rustfmt::skip]
+            return Err(format!(
+                "ISO 9660 names are limited to A-Z, 0-9, '_', and a single '.': {:?}", component)
+                .into());
+        }
+
+        let (stem_len, combined_len) = match name.split_once('.') {
+            Some((stem, ext)) => (stem.len().max(ext.len()), stem.len() + 1 + ext.len()),
+            None => (name.len(), name.len()),
+        };
+        if stem_len > 8 && combined_len > 31 {
+            #[rustfmt::skip]
+            return Err(format!(
+                "Component exceeds both the ISO 9660 Level 1 (8.3) and Level 2 (31) limits: {:?}",
+                component).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// The given path's components are short enough for the Joliet extensions to ISO 9660
+///
+/// ## Use For:
+///  * Output paths destined for optical media which will be read with Joliet-aware software
+///    (i.e. anything from the last ~25 years), where ISO 9660's own 8.3 limit would be too
+///    restrictive.
+///
+/// Only checks the [`MAX_JOLIET_LEN`](constant.MAX_JOLIET_LEN.html)-code-unit filename limit the
+/// Joliet spec actually guarantees; combine with other checks (e.g. total path length) as needed.
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn path_valid_joliet<P: AsRef<Path> + ?Sized>(value: &P) -> Result<(), OsString> {
+    let path = value.as_ref();
+
+    for component in path.components() {
+        if let Component::Normal(string) = component {
+            let name = string.to_string_lossy();
+            let len = name.encode_utf16().count();
+            if len > MAX_JOLIET_LEN {
+                #[rustfmt::skip]
+                return Err(format!(
+                    "Component exceeds Joliet's {}-UCS-2-unit limit ({} units): {:?}",
+                    MAX_JOLIET_LEN, len, component).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The given path is short enough for the UDF filesystem used on DVDs and newer optical media
+///
+/// ## Use For:
+///  * Output paths destined for UDF-formatted optical media.
+///
+/// Only checks the [`MAX_UDF_PATH_LEN`](constant.MAX_UDF_PATH_LEN.html)-byte total path limit.
+#[allow(dead_code)] // TEMPLATE:REMOVE
+pub fn path_valid_udf<P: AsRef<Path> + ?Sized>(value: &P) -> Result<(), OsString> {
+    let path = value.as_ref();
+
+    if path.as_os_str().len() > MAX_UDF_PATH_LEN {
+        #[rustfmt::skip]
+        return Err(format!("Path exceeds UDF's {}-byte limit ({} bytes): {:?}",
+            MAX_UDF_PATH_LEN, path.as_os_str().len(), path).into());
+    }
+
+    Ok(())
+}
+
+/// A configurable set of filename/path portability limits
+///
+/// [`path_valid_portable`]/[`filename_valid_portable`] bake in a single set of assumptions (a
+/// 32,760-byte path limit, a 255-character component limit, the VFAT/exFAT/NTFS invalid-character
+/// union, and DOS reserved names) regardless of where the output is actually going to end up.
+/// Build a `PortabilityProfile` with a named constructor for a known target filesystem -- or by
+/// hand via the public fields -- when that one-size-fits-all union is stricter than you need.
+///
+/// `strict_union()` reproduces today's `path_valid_portable`/`filename_valid_portable` behaviour.
+#[derive(Clone, Debug)]
+pub struct PortabilityProfile {
+    /// Maximum total path length, in bytes
+    pub max_path_len: usize,
+    /// Maximum length of a single path component, in bytes
+    pub max_component_len: usize,
+    /// Bytes which may not appear in a path component
+    pub forbidden_bytes: &'static [u8],
+    /// Whether a component ending in a space or period should be rejected (a Windows quirk)
+    pub forbid_trailing_space_or_period: bool,
+    /// Whether `RESERVED_DOS_FILENAMES` should be rejected
+    pub forbid_dos_reserved_names: bool,
+}
+
+impl PortabilityProfile {
+    /// The limits `path_valid_portable`/`filename_valid_portable` enforce today: the union of
+    /// VFAT/exFAT/NTFS restrictions plus Windows reserved names, regardless of target filesystem
+    #[must_use]
+    pub fn strict_union() -> Self {
+        #[rustfmt::skip]
+        const FORBIDDEN: &[u8] = &[
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f, 0x7f,
+            b'"', b'*', b'<', b'>', b'?', b'|', b'/', b':', b'\\',
+        ];
+        Self {
+            max_path_len: 32760,
+            max_component_len: 255,
+            forbidden_bytes: FORBIDDEN,
+            forbid_trailing_space_or_period: true,
+            forbid_dos_reserved_names: true,
+        }
+    }
+
+    /// FAT32, as found on most flash drives, SD cards, and UEFI system partitions
+    #[must_use]
+    pub fn fat32() -> Self { Self { max_path_len: 260, ..Self::strict_union() } }
+
+    /// exFAT, FAT32's successor used on larger flash drives and SDXC cards
+    #[must_use]
+    pub fn exfat() -> Self { Self::strict_union() }
+
+    /// NTFS, Windows' native filesystem
+    #[must_use]
+    pub fn ntfs() -> Self { Self::strict_union() }
+
+    /// ext4, the common default on Linux -- permissive aside from `NUL` and `/`
+    #[must_use]
+    pub fn ext4() -> Self {
+        Self {
+            max_path_len: 4096,
+            max_component_len: 255,
+            forbidden_bytes: &[0x00, b'/'],
+            forbid_trailing_space_or_period: false,
+            forbid_dos_reserved_names: false,
+        }
+    }
+
+    /// eCryptFS-encrypted home directories, as used by Ubuntu's encrypted-home feature, which
+    /// imposes a 143-character filename cap once filename encryption is enabled
+    /// ([LP#344878](https://bugs.launchpad.net/ecryptfs/+bug/344878))
+    #[must_use]
+    pub fn ecryptfs() -> Self { Self { max_component_len: 143, ..Self::ext4() } }
+
+    /// Validate a full path against this profile
+    pub fn validate_path<P: AsRef<Path> + ?Sized>(&self, value: &P) -> Result<(), OsString> {
+        let path = value.as_ref();
+
+        if path.as_os_str().is_empty() {
+            return Err("Path is empty".into());
+        }
+        if path.as_os_str().len() > self.max_path_len {
+            #[allow(clippy::use_debug)]
+            return Err(format!("Path is too long ({} chars): {:?}",
+                path.as_os_str().len(), path).into());
+        }
+
+        for component in path.components() {
+            if let Component::Normal(string) = component {
+                self.validate_filename(string)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate a single filename/path component against this profile
+    pub fn validate_filename<P: AsRef<Path> + ?Sized>(&self, value: &P) -> Result<(), OsString> {
+        let path = value.as_ref();
+        let os_str = path.as_os_str();
+
+        if os_str.len() > self.max_component_len {
+            #[rustfmt::skip]
+            return Err(format!("File/folder name is too long ({} chars): {}",
+                os_str.len(), path.display()).into());
+        }
+
+        let lossy_str = match os_str.to_str() {
+            Some(string) => string,
+            None => {
+                return Err("File/folder names containing non-UTF8 characters aren't portable"
+                    .into())
+            },
+        };
+        if lossy_str.is_empty() {
+            return Err("File/folder name is empty".into());
+        }
+
+        if self.forbid_trailing_space_or_period {
+            if let Some(last_char) = lossy_str.chars().last() {
+                if last_char == ' ' || last_char == '.' {
+                    return Err(
+                        "This profile forbids path components ending with spaces/periods".into());
+                }
+            }
+        }
+
+        if lossy_str.as_bytes().iter().any(|byte| self.forbidden_bytes.contains(byte)) {
+            #[rustfmt::skip]
+            return Err(format!("Path component contains invalid characters: {}",
+                path.display()).into());
+        }
+
+        if self.forbid_dos_reserved_names {
+            if let Some(file_stem) = path.file_stem() {
+                let stem = file_stem.to_string_lossy().to_uppercase();
+                if RESERVED_DOS_FILENAMES.iter().any(|&x| x == stem) {
+                    return Err(format!("Filename is reserved on Windows: {:?}", file_stem).into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build a `StructOpt`/`Clap` `validator_os`-compatible closure bound to this profile's
+    /// `validate_path`
+    ///
+    /// `#[structopt(validator_os = ...)]` wants something with the signature `fn(&OsStr) ->
+    /// Result<(), OsString>`; this lets a profile built at runtime (rather than a `const fn`) be
+    /// used that way without every caller writing the same wrapper closure.
+    #[must_use]
+    pub fn as_path_validator(&self) -> impl Fn(&OsStr) -> Result<(), OsString> + 'static {
+        let profile = self.clone();
+        move |value: &OsStr| profile.validate_path(value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::wildcard_imports, clippy::panic, clippy::result_expect_used)] // OK for tests
@@ -298,7 +864,29 @@ mod tests {
     #[test]
     #[cfg(windows)]
     fn path_output_dir_basic_functionality() {
-        unimplemented!("TODO: Implement Windows version of path_output_dir");
+        assert!(path_output_dir(OsStr::new(r"C:\Windows\Temp")).is_ok());      // Writable temp dir
+        assert!(path_output_dir(OsStr::new(r"C:\Windows\System32")).is_err()); // Denied system dir
+        assert!(path_output_dir(OsStr::new(r"C:\nonexistent_test_path")).is_err()); // Missing path
+    }
+
+    // ---- path_writable ----
+
+    #[test]
+    #[cfg(unix)]
+    #[rustfmt::skip]
+    fn path_writable_basic_functionality() {
+        assert!(path_writable(OsStr::new("/tmp")).is_ok());                   // OK existing folder
+        assert!(path_writable(OsStr::new("/tmp/does_not_exist_yet")).is_ok()); // OK nonexistent file
+        assert!(path_writable(OsStr::new("/etc/shadow")).is_err());           // Denied existing file
+        assert!(path_writable(OsStr::new("/etc/ssl/private/nope")).is_err()); // Denied parent dir
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn path_writable_basic_functionality() {
+        assert!(path_writable(OsStr::new(r"C:\Windows\Temp")).is_ok());
+        assert!(path_writable(OsStr::new(r"C:\Windows\Temp\does_not_exist_yet")).is_ok());
+        assert!(path_writable(OsStr::new(r"C:\Windows\System32\nope")).is_err());
     }
 
     // ---- path_readable_file ----
@@ -343,6 +931,47 @@ mod tests {
         // TODO: Unpaired surrogate path that actually IS valid
     }
 
+    // ---- path_readable_file_utf8 ----
+
+    #[cfg(all(unix, feature = "utf8-paths"))]
+    #[test]
+    fn path_readable_file_utf8_accepts_valid_utf8() {
+        assert!(path_readable_file_utf8(OsStr::new("/bin/sh")).is_ok());
+    }
+
+    #[cfg(all(unix, feature = "utf8-paths"))]
+    #[test]
+    fn path_readable_file_utf8_refuses_non_utf8_bytes() {
+        assert!(path_readable_file_utf8(OsStr::from_bytes(b"/not\xffutf8")).is_err());
+    }
+
+    // ---- path_readable_file_or_stdin / InputSource ----
+
+    #[test]
+    fn path_readable_file_or_stdin_accepts_dash_regardless_of_existence() {
+        assert!(path_readable_file_or_stdin(OsStr::new("-")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn path_readable_file_or_stdin_defers_to_path_readable_file_otherwise() {
+        assert!(path_readable_file_or_stdin(OsStr::new("/bin/sh")).is_ok());
+        assert!(path_readable_file_or_stdin(OsStr::new("/nonexistant_test_path")).is_err());
+    }
+
+    #[test]
+    fn input_source_resolve_handles_interleaved_dashes() {
+        // `my_utility -f header.dat -f - -f footer.dat` should resolve `-` independently each
+        // time it appears, rather than e.g. only honouring the first or last occurrence.
+        let args = ["header.dat", "-", "footer.dat", "-"];
+        let resolved: Vec<_> = args.iter().map(|a| InputSource::resolve(OsStr::new(a))).collect();
+
+        assert_eq!(resolved[0], InputSource::Path(PathBuf::from("header.dat")));
+        assert_eq!(resolved[1], InputSource::Stdin);
+        assert_eq!(resolved[2], InputSource::Path(PathBuf::from("footer.dat")));
+        assert_eq!(resolved[3], InputSource::Stdin);
+    }
+
     // ---- filename_valid_portable ----
 
     #[rustfmt::skip]
@@ -436,6 +1065,44 @@ mod tests {
         assert!(path_valid_portable(&OsString::from_wide(&[0xd800])).is_ok());
     }
 
+    // ---- filename_valid_portable_normalized ----
+
+    #[test]
+    fn filename_valid_portable_normalized_ignore_skips_the_nfc_check() {
+        // NFD "e" + combining acute accent, which is not NFC
+        let test_str = "e\u{0301}";
+        assert!(filename_valid_portable_normalized(
+            OsStr::new(test_str), NormalizationPolicy::Ignore).is_ok());
+    }
+
+    #[test]
+    fn filename_valid_portable_normalized_require_nfc_accepts_precomposed_forms() {
+        // Precomposed "\u{e9}" (LATIN SMALL LETTER E WITH ACUTE), already NFC
+        let test_str = "\u{e9}";
+        assert!(filename_valid_portable_normalized(
+            OsStr::new(test_str), NormalizationPolicy::RequireNfc).is_ok());
+    }
+
+    #[test]
+    fn filename_valid_portable_normalized_require_nfc_refuses_decomposed_forms() {
+        // NFD "e" + combining acute accent, which is not NFC
+        let test_str = "e\u{0301}";
+        assert!(filename_valid_portable_normalized(
+            OsStr::new(test_str), NormalizationPolicy::RequireNfc).is_err());
+    }
+
+    #[test]
+    fn filename_valid_portable_normalized_require_nfc_falls_back_on_inconclusive_quick_check() {
+        // "a" + COMBINING GRAPHEME JOINER + combining grave accent: `is_nfc_quick` can't decide
+        // from the combining classes alone (it returns `Maybe`), but the string is already NFC,
+        // since U+034F has no composition with a preceding "a" or a following combining mark.
+        let test_str = "a\u{34f}\u{300}";
+        assert_eq!(is_nfc_quick(test_str.chars()), IsNormalized::Maybe,
+            "this test relies on {:?} quick-checking as inconclusive", test_str);
+        assert!(filename_valid_portable_normalized(
+            OsStr::new(test_str), NormalizationPolicy::RequireNfc).is_ok());
+    }
+
     // ---- path_valid_portable ----
 
     #[test]
@@ -500,6 +1167,23 @@ mod tests {
         assert!(path_valid_portable(OsStr::new(&test_string)).is_ok());
     }
 
+    #[test]
+    fn path_valid_portable_refuses_reserved_names_in_any_position() {
+        // `CON/foo.txt` is just as broken as `foo/CON`, not just the final component, since
+        // `path_valid_portable` validates every `Normal` component via `filename_valid_portable`
+        assert!(path_valid_portable(OsStr::new("CON/foo.txt")).is_err());
+        assert!(path_valid_portable(OsStr::new("foo/CON")).is_err());
+        assert!(path_valid_portable(OsStr::new("foo/CON/bar.txt")).is_err());
+    }
+
+    #[test]
+    fn path_valid_portable_refuses_trailing_space_or_period_in_any_position() {
+        assert!(path_valid_portable(OsStr::new("foo./bar")).is_err());
+        assert!(path_valid_portable(OsStr::new("foo /bar")).is_err());
+        assert!(path_valid_portable(OsStr::new("foo/bar.")).is_err());
+        assert!(path_valid_portable(OsStr::new("foo/bar")).is_ok());
+    }
+
     #[cfg(unix)]
     #[test]
     fn path_valid_portable_accepts_non_utf8_bytes() {